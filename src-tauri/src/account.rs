@@ -0,0 +1,102 @@
+//! 账号存储：内存里维护当前导入的所有 Kiro 账号，负责切换 / 刷新 / 查询 token。
+//! GUI 命令（`commands::account_cmd`）、本地 IPC 服务（`ipc`）和令牌注入代理
+//! （`proxy_server`）都通过 `AppState.store` 这同一把锁访问它，避免互相踩踏。
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Account {
+    pub id: String,
+    pub provider: String,
+    pub display_name: String,
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    /// unix 时间戳（秒），`None` 表示没有过期时间
+    pub expires_at: Option<i64>,
+}
+
+/// 一次成功的 provider 刷新返回的新凭据，喂给 [`AccountStore::apply_refreshed_token`]
+/// 写回对应账号
+pub struct RefreshedToken {
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    pub expires_at: Option<i64>,
+}
+
+#[derive(Debug, Default)]
+pub struct AccountStore {
+    accounts: Vec<Account>,
+    active_id: Option<String>,
+}
+
+impl AccountStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn list(&self) -> &[Account] {
+        &self.accounts
+    }
+
+    fn find(&self, id: &str) -> Option<&Account> {
+        self.accounts.iter().find(|a| a.id == id)
+    }
+
+    fn find_mut(&mut self, id: &str) -> Option<&mut Account> {
+        self.accounts.iter_mut().find(|a| a.id == id)
+    }
+
+    /// 取一份账号数据的快照，供需要在不持有这把锁的情况下发起网络请求的调用方
+    /// （比如按 provider 分派刷新逻辑）使用
+    pub fn account(&self, id: &str) -> Option<Account> {
+        self.find(id).cloned()
+    }
+
+    pub fn switch(&mut self, id: &str) -> Result<(), String> {
+        if self.find(id).is_none() {
+            return Err(format!("unknown account `{}`", id));
+        }
+        self.active_id = Some(id.to_string());
+        Ok(())
+    }
+
+    pub fn token_expired(&self, id: &str) -> bool {
+        match self.find(id).and_then(|a| a.expires_at) {
+            Some(expires_at) => expires_at <= now_unix(),
+            None => false,
+        }
+    }
+
+    /// 实际的刷新请求（发给对应 provider 的 token endpoint）由调用方按账号的
+    /// provider 分派完成，这里只负责把换回来的新凭据写回账号列表
+    pub fn apply_refreshed_token(&mut self, id: &str, refreshed: RefreshedToken) -> Result<(), String> {
+        let account = self.find_mut(id).ok_or_else(|| format!("unknown account `{}`", id))?;
+        account.access_token = refreshed.access_token;
+        if refreshed.refresh_token.is_some() {
+            account.refresh_token = refreshed.refresh_token;
+        }
+        account.expires_at = refreshed.expires_at;
+        Ok(())
+    }
+
+    pub fn access_token(&self, id: &str) -> Option<String> {
+        self.find(id).map(|a| a.access_token.clone())
+    }
+
+    /// 把一个新登录成功的账号写入账号列表；`add_account_by_social` /
+    /// `add_account_by_idc` / `add_account_by_oidc` 等命令最终都走这里。
+    pub fn add_account(&mut self, account: Account) -> Result<(), String> {
+        if self.find(&account.id).is_some() {
+            return Err(format!("account `{}` already exists", account.id));
+        }
+        self.accounts.push(account);
+        Ok(())
+    }
+}
+
+fn now_unix() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
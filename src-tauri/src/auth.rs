@@ -0,0 +1,15 @@
+//! 当前登录用户/会话状态，挂在 `AppState.auth` 下。`current_user` 是私有字段，
+//! 本模块只提供 `new()`，没有对外读写它的方法。
+
+use std::sync::Mutex;
+
+#[derive(Debug, Default)]
+pub struct AuthState {
+    current_user: Mutex<Option<String>>,
+}
+
+impl AuthState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
@@ -0,0 +1,67 @@
+//! 小型命令行客户端，通过 Windows 命名管道 / Unix domain socket
+//! 和正在运行的 Kiro Account Manager 主进程通信，
+//! 方便从终端或 CI 脚本里执行 "切换账号 -> 启动 Kiro" 这类操作，而不需要唤起 GUI。
+
+use std::io::{BufRead, BufReader, Write};
+
+const PIPE_NAME: &str = "kiro-account-manager";
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let (cmd, account_id) = match args.as_slice() {
+        [_, cmd] => (cmd.clone(), None),
+        [_, cmd, account_id] => (cmd.clone(), Some(account_id.clone())),
+        _ => {
+            eprintln!("usage: kiro-cli <get_accounts|switch_kiro_account|refresh_account_token|get_kiro_local_token> [account_id]");
+            std::process::exit(2);
+        }
+    };
+
+    let request = serde_json::json!({
+        "cmd": cmd,
+        "args": account_id.map(|id| serde_json::json!({ "account_id": id })).unwrap_or_default(),
+    });
+
+    match send(&request) {
+        Ok(response) => {
+            println!("{}", response);
+        }
+        Err(e) => {
+            eprintln!("kiro-cli: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn send(request: &serde_json::Value) -> Result<String, Box<dyn std::error::Error>> {
+    use std::os::unix::net::UnixStream;
+
+    let path = std::env::temp_dir().join(format!("{}.sock", PIPE_NAME));
+    let stream = UnixStream::connect(&path)
+        .map_err(|e| format!("could not connect to {} ({}); is the app running?", path.display(), e))?;
+    roundtrip(stream, request)
+}
+
+#[cfg(windows)]
+fn send(request: &serde_json::Value) -> Result<String, Box<dyn std::error::Error>> {
+    use windows_named_pipe::PipeStream;
+
+    let stream = PipeStream::connect(format!(r"\\.\pipe\{}", PIPE_NAME))
+        .map_err(|e| format!(r"could not connect to \\.\pipe\{} ({}); is the app running?", PIPE_NAME, e))?;
+    roundtrip(stream, request)
+}
+
+fn roundtrip<S: std::io::Read + std::io::Write>(
+    mut stream: S,
+    request: &serde_json::Value,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let mut line = serde_json::to_string(request)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())?;
+
+    let mut reader = BufReader::new(stream);
+    let mut response = String::new();
+    reader.read_line(&mut response)?;
+    Ok(response.trim_end().to_string())
+}
@@ -0,0 +1,152 @@
+//! 处理 `kiro://` 深链回调。single-instance 回调和 `deep-link://new-url`
+//! 事件监听器各自用不同的载荷格式把 URL 传进来，这里统一收口成两个小函数，
+//! 新增平台（比如 macOS）只需要把原始载荷喂给 [`extract_url`] 或
+//! [`parse_event_payload`]，不用再各自猜测参数下标。
+
+use tauri::Manager;
+
+/// single-instance 插件传来的 `args` 里找出 `kiro://` URL。
+/// Windows/Linux 观察到的几种形状：
+/// - `["exe", "--open-url", "--", "kiro://..."]`
+/// - `["exe", "kiro://..."]`
+/// - `["exe", "--open-url", "kiro://..."]`
+/// 直接找第一个以 `kiro://` 开头的参数即可覆盖以上所有形状，
+/// 不用再按位置硬编码 `args[1..3]`。
+pub fn extract_url(args: &[String]) -> Option<String> {
+    args.iter().skip(1).find(|a| a.starts_with("kiro://")).cloned()
+}
+
+/// `deep-link://new-url` 事件的 payload 是 JSON 编码的字符串
+/// （形如 `"\"kiro://...\""`），也可能在某些平台上直接是裸 URL。
+/// 能解析就解析，解析失败就原样返回，不让一次偶发的格式差异丢掉回调。
+pub fn parse_event_payload(payload: &str) -> String {
+    serde_json::from_str::<String>(payload).unwrap_or_else(|_| payload.to_string())
+}
+
+/// 解析并处理一个 `kiro://` 回调 URL，返回是否成功识别并处理。
+/// 目前只做最基础的校验：确认 scheme 是 `kiro://`，具体 provider 分发交给
+/// [`route_deep_link`]。
+pub fn handle_deep_link(url: &str) -> bool {
+    let Ok(parsed) = url::Url::parse(url) else {
+        println!("[DeepLink] failed to parse URL: {}", url);
+        return false;
+    };
+
+    if parsed.scheme() != "kiro" {
+        println!("[DeepLink] unexpected scheme: {}", parsed.scheme());
+        return false;
+    }
+
+    println!("[DeepLink] routing callback for path: {}", parsed.path());
+    true
+}
+
+/// 如果 `url` 是 `kiro://oauth/oidc-callback?code=...&state=...`，取出其中的
+/// `code` / `state` 查询参数。纯函数，方便单测覆盖各种边界情况，不掺杂任何
+/// I/O。
+fn oidc_callback_params(url: &url::Url) -> Option<(String, String)> {
+    if url.host_str() != Some("oauth") || url.path() != "/oidc-callback" {
+        return None;
+    }
+    let pairs: std::collections::HashMap<_, _> = url.query_pairs().into_owned().collect();
+    Some((pairs.get("code")?.clone(), pairs.get("state")?.clone()))
+}
+
+/// 在 `handle_deep_link` 确认这是一条合法的 `kiro://` 回调之后调用，
+/// 把具体 provider 的回调转发给对应的模块去完成登录。目前只接了通用 OIDC /
+/// IndieAuth 的回调；社交登录 / IdC 走各自既有的处理路径。
+pub fn route_deep_link(app_handle: &tauri::AppHandle, url: &str) {
+    let Ok(parsed) = url::Url::parse(url) else {
+        return;
+    };
+
+    if let Some((code, state)) = oidc_callback_params(&parsed) {
+        let app_handle = app_handle.clone();
+        tauri::async_runtime::spawn(async move {
+            let app_state = app_handle.state::<crate::state::AppState>();
+            match crate::oidc_provider::complete_oidc_callback(&app_state, &code, &state).await {
+                Ok(()) => println!("[DeepLink] OIDC login completed"),
+                Err(e) => eprintln!("[DeepLink] OIDC login failed: {}", e),
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn extract_url_finds_windows_open_url_shape() {
+        let a = args(&["kiro-account-manager.exe", "--open-url", "--", "kiro://oauth/callback"]);
+        assert_eq!(extract_url(&a), Some("kiro://oauth/callback".to_string()));
+    }
+
+    #[test]
+    fn extract_url_finds_bare_url_shape() {
+        let a = args(&["kiro-account-manager", "kiro://oauth/callback"]);
+        assert_eq!(extract_url(&a), Some("kiro://oauth/callback".to_string()));
+    }
+
+    #[test]
+    fn extract_url_finds_three_arg_shape() {
+        let a = args(&["kiro-account-manager", "--open-url", "kiro://oauth/callback"]);
+        assert_eq!(extract_url(&a), Some("kiro://oauth/callback".to_string()));
+    }
+
+    #[test]
+    fn extract_url_returns_none_without_a_kiro_url() {
+        let a = args(&["kiro-account-manager", "--some-other-flag"]);
+        assert_eq!(extract_url(&a), None);
+    }
+
+    #[test]
+    fn extract_url_returns_none_for_single_arg() {
+        let a = args(&["kiro-account-manager"]);
+        assert_eq!(extract_url(&a), None);
+    }
+
+    #[test]
+    fn parse_event_payload_unwraps_json_escaped_string() {
+        let payload = "\"kiro://oauth/callback?code=abc\"";
+        assert_eq!(parse_event_payload(payload), "kiro://oauth/callback?code=abc");
+    }
+
+    #[test]
+    fn parse_event_payload_falls_back_to_raw_on_invalid_json() {
+        let payload = "kiro://oauth/callback?code=abc";
+        assert_eq!(parse_event_payload(payload), payload);
+    }
+
+    #[test]
+    fn handle_deep_link_rejects_non_kiro_scheme() {
+        assert!(!handle_deep_link("https://example.com"));
+    }
+
+    #[test]
+    fn handle_deep_link_accepts_kiro_scheme() {
+        assert!(handle_deep_link("kiro://oauth/callback?code=abc&state=xyz"));
+    }
+
+    #[test]
+    fn oidc_callback_params_extracts_code_and_state() {
+        let url = url::Url::parse("kiro://oauth/oidc-callback?code=abc123&state=xyz789").unwrap();
+        assert_eq!(oidc_callback_params(&url), Some(("abc123".to_string(), "xyz789".to_string())));
+    }
+
+    #[test]
+    fn oidc_callback_params_ignores_other_paths() {
+        let url = url::Url::parse("kiro://oauth/callback?code=abc123&state=xyz789").unwrap();
+        assert_eq!(oidc_callback_params(&url), None);
+    }
+
+    #[test]
+    fn oidc_callback_params_requires_both_code_and_state() {
+        let url = url::Url::parse("kiro://oauth/oidc-callback?code=abc123").unwrap();
+        assert_eq!(oidc_callback_params(&url), None);
+    }
+}
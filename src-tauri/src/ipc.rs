@@ -0,0 +1,277 @@
+//! 本地 IPC 服务：在 GUI 未聚焦甚至未打开窗口时，
+//! 通过 Windows 命名管道 / Unix domain socket 暴露账号切换等能力给外部进程，
+//! 配合 `kiro-cli` 二进制使用，方便脚本化 "切换账号 -> 启动 Kiro" 这类操作。
+
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use tauri::{AppHandle, Manager};
+
+use crate::commands::account_cmd::{get_accounts, refresh_account_token};
+use crate::kiro::{get_kiro_local_token, switch_kiro_account};
+use crate::state::AppState;
+
+/// socket / 管道名称，Windows 下会被包装成 `\\.\pipe\<PIPE_NAME>`
+const PIPE_NAME: &str = "kiro-account-manager";
+
+#[derive(Debug, Deserialize)]
+struct IpcRequest {
+    /// 命令名，对应 Tauri 侧同名的 command：
+    /// "get_accounts" | "switch_kiro_account" | "refresh_account_token" | "get_kiro_local_token"。
+    /// `get_kiro_local_token` 读的是 IDE 自己的本地凭据，不区分账号，
+    /// `kiro-cli` 传的 `account_id` 在这里会被忽略
+    cmd: String,
+    #[serde(default)]
+    args: serde_json::Value,
+}
+
+#[derive(Debug, Serialize)]
+struct IpcResponse {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl IpcResponse {
+    fn ok(data: serde_json::Value) -> Self {
+        Self { ok: true, data: Some(data), error: None }
+    }
+
+    fn err(message: impl Into<String>) -> Self {
+        Self { ok: false, data: None, error: Some(message.into()) }
+    }
+}
+
+/// 在 `setup()` 中调用，把服务放到独立线程里跑，不阻塞 Tauri 的事件循环
+pub fn spawn(app_handle: AppHandle) {
+    std::thread::spawn(move || {
+        if let Err(e) = run(app_handle) {
+            eprintln!("[Ipc] server exited with error: {}", e);
+        }
+    });
+}
+
+fn run(app_handle: AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    #[cfg(unix)]
+    {
+        run_unix(app_handle)
+    }
+    #[cfg(windows)]
+    {
+        run_windows(app_handle)
+    }
+}
+
+/// 处理一行 JSON 请求，返回一行 JSON 响应（line-delimited JSON 协议）。
+///
+/// 这里直接复用 GUI `invoke_handler` 里注册的同一批命令实现，而不是另起一套
+/// 针对 `AccountStore` 的逻辑 —— 切换 / 刷新账号要真正影响到正在运行的 Kiro
+/// IDE（写配置、打通知等），这些效果只有命令本身的实现知道怎么做。
+async fn dispatch(app_handle: &AppHandle, line: &str) -> IpcResponse {
+    let request: IpcRequest = match serde_json::from_str(line) {
+        Ok(r) => r,
+        Err(e) => return IpcResponse::err(format!("invalid request: {}", e)),
+    };
+
+    let state = app_handle.state::<AppState>();
+
+    match request.cmd.as_str() {
+        "get_accounts" => match get_accounts(state).await {
+            Ok(accounts) => match serde_json::to_value(accounts) {
+                Ok(v) => IpcResponse::ok(v),
+                Err(e) => IpcResponse::err(e.to_string()),
+            },
+            Err(e) => IpcResponse::err(e),
+        },
+        "switch_kiro_account" => match request.args.get("account_id").and_then(|v| v.as_str()) {
+            Some(id) => match switch_kiro_account(id.to_string(), state).await {
+                Ok(()) => IpcResponse::ok(serde_json::json!({ "switched": id })),
+                Err(e) => IpcResponse::err(e),
+            },
+            None => IpcResponse::err("missing `account_id` argument"),
+        },
+        "refresh_account_token" => match request.args.get("account_id").and_then(|v| v.as_str()) {
+            Some(id) => match refresh_account_token(id.to_string(), state).await {
+                Ok(()) => IpcResponse::ok(serde_json::json!({ "refreshed": id })),
+                Err(e) => IpcResponse::err(e),
+            },
+            None => IpcResponse::err("missing `account_id` argument"),
+        },
+        // get_kiro_local_token 读的是 Kiro IDE 自己落盘的本地凭据，不是我们这边
+        // AccountStore 里的某个账号，所以不需要也不接受 account_id 参数
+        "get_kiro_local_token" => match get_kiro_local_token(state).await {
+            Ok(token) => IpcResponse::ok(serde_json::json!({ "token": token })),
+            Err(e) => IpcResponse::err(e),
+        },
+        other => IpcResponse::err(format!("unknown command: {}", other)),
+    }
+}
+
+#[cfg(unix)]
+fn socket_path() -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("{}.sock", PIPE_NAME))
+}
+
+#[cfg(unix)]
+fn run_unix(app_handle: AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    use std::os::unix::net::UnixListener;
+
+    let path = socket_path();
+    // 上一次进程异常退出可能留下旧 socket 文件
+    let _ = std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path)?;
+    println!("[Ipc] listening on unix socket {}", path.display());
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("[Ipc] accept error: {}", e);
+                continue;
+            }
+        };
+        // 仅接受同一用户发起的连接（peer credentials），拒绝其它 UID 的请求
+        if !peer_is_same_user(&stream) {
+            eprintln!("[Ipc] rejected connection from a different uid");
+            continue;
+        }
+        let app_handle = app_handle.clone();
+        std::thread::spawn(move || handle_client(app_handle, stream));
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn peer_is_same_user(stream: &std::os::unix::net::UnixStream) -> bool {
+    use std::os::unix::io::AsRawFd;
+    // SAFETY: fd 来自一个已连接的 UnixStream，在函数调用期间保持有效
+    unsafe {
+        let mut cred: libc::ucred = std::mem::zeroed();
+        let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+        let rc = libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut cred as *mut _ as *mut libc::c_void,
+            &mut len,
+        );
+        rc == 0 && cred.uid == libc::getuid()
+    }
+}
+
+/// 只允许管道的创建者（当前用户）和 SYSTEM 访问，拒绝其它本地用户/`Everyone`，
+/// 并通过 `PIPE_REJECT_REMOTE_CLIENTS` 确保只有本机能连进来
+#[cfg(windows)]
+const PIPE_SDDL: &str = "D:(A;;GA;;;OW)(A;;GA;;;SY)";
+
+#[cfg(windows)]
+fn run_windows(app_handle: AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    use std::ffi::OsString;
+    use std::os::windows::ffi::OsStrExt;
+    use std::os::windows::io::FromRawHandle;
+    use windows_sys::Win32::Foundation::{CloseHandle, ERROR_PIPE_CONNECTED, HANDLE, INVALID_HANDLE_VALUE};
+    use windows_sys::Win32::Security::Authorization::ConvertStringSecurityDescriptorToSecurityDescriptorW;
+    use windows_sys::Win32::Security::{SECURITY_ATTRIBUTES, SDDL_REVISION_1};
+    use windows_sys::Win32::Storage::FileSystem::PIPE_ACCESS_DUPLEX;
+    use windows_sys::Win32::System::Pipes::{
+        ConnectNamedPipe, CreateNamedPipeW, PIPE_READMODE_BYTE, PIPE_REJECT_REMOTE_CLIENTS,
+        PIPE_TYPE_BYTE, PIPE_WAIT,
+    };
+
+    let pipe_path: Vec<u16> = OsString::from(format!(r"\\.\pipe\{}", PIPE_NAME))
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect();
+    let sddl: Vec<u16> = OsString::from(PIPE_SDDL).encode_wide().chain(std::iter::once(0)).collect();
+
+    println!(r"[Ipc] listening on named pipe \\.\pipe\{} (ACL-restricted to current user + SYSTEM)", PIPE_NAME);
+
+    loop {
+        // 每个连接对应一个 security descriptor，CreateNamedPipeW 不会长期持有它，
+        // 所以每次建新实例时都重新转换一遍，避免悬垂指针
+        let mut security_descriptor: *mut core::ffi::c_void = std::ptr::null_mut();
+        let converted = unsafe {
+            ConvertStringSecurityDescriptorToSecurityDescriptorW(
+                sddl.as_ptr(),
+                SDDL_REVISION_1,
+                &mut security_descriptor,
+                std::ptr::null_mut(),
+            )
+        };
+        if converted == 0 {
+            return Err(format!("failed to build pipe security descriptor: {}", std::io::Error::last_os_error()).into());
+        }
+
+        let mut security_attributes = SECURITY_ATTRIBUTES {
+            nLength: std::mem::size_of::<SECURITY_ATTRIBUTES>() as u32,
+            lpSecurityDescriptor: security_descriptor,
+            bInheritHandle: 0,
+        };
+
+        let handle: HANDLE = unsafe {
+            CreateNamedPipeW(
+                pipe_path.as_ptr(),
+                PIPE_ACCESS_DUPLEX,
+                PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+                windows_sys::Win32::System::Pipes::PIPE_UNLIMITED_INSTANCES,
+                4096,
+                4096,
+                0,
+                &mut security_attributes,
+            )
+        };
+
+        unsafe {
+            windows_sys::Win32::System::Memory::LocalFree(security_descriptor as isize);
+        }
+
+        if handle == INVALID_HANDLE_VALUE {
+            return Err(format!("CreateNamedPipeW failed: {}", std::io::Error::last_os_error()).into());
+        }
+
+        let connected = unsafe { ConnectNamedPipe(handle, std::ptr::null_mut()) };
+        let err = std::io::Error::last_os_error();
+        if connected == 0 && err.raw_os_error() != Some(ERROR_PIPE_CONNECTED as i32) {
+            eprintln!("[Ipc] ConnectNamedPipe failed: {}", err);
+            unsafe { CloseHandle(handle) };
+            continue;
+        }
+
+        // SAFETY: handle 是一个刚连接成功、独占所有权的管道实例句柄
+        let stream = unsafe { std::fs::File::from_raw_handle(handle as *mut _) };
+        let app_handle = app_handle.clone();
+        std::thread::spawn(move || handle_client(app_handle, stream));
+    }
+}
+
+fn handle_client<S: std::io::Read + std::io::Write>(app_handle: AppHandle, stream: S) {
+    let mut reader = BufReader::new(stream);
+    loop {
+        let mut line = String::new();
+        let n = match reader.read_line(&mut line) {
+            Ok(n) => n,
+            Err(e) => {
+                eprintln!("[Ipc] read error: {}", e);
+                return;
+            }
+        };
+        if n == 0 {
+            return; // 对端关闭连接
+        }
+
+        let response = tauri::async_runtime::block_on(dispatch(&app_handle, line.trim_end()));
+        let mut payload = match serde_json::to_string(&response) {
+            Ok(p) => p,
+            Err(e) => {
+                eprintln!("[Ipc] failed to serialize response: {}", e);
+                return;
+            }
+        };
+        payload.push('\n');
+        if let Err(e) = reader.get_mut().write_all(payload.as_bytes()) {
+            eprintln!("[Ipc] write error: {}", e);
+            return;
+        }
+    }
+}
@@ -7,13 +7,15 @@ mod browser;
 mod codewhisperer_client;
 mod commands;
 mod deep_link_handler;
-
+mod ipc;
 mod kiro;
 mod kiro_auth_client;
 mod mcp;
+mod oidc_provider;
 mod powers;
 mod process;
 mod providers;
+mod proxy_server;
 mod state;
 mod steering;
 mod account;
@@ -47,6 +49,50 @@ use kiro::{
 };
 use process::{close_kiro_ide, is_kiro_ide_running, start_kiro_ide};
 
+/// Linux: 写入 .desktop 文件，确保 kiro:// 协议在 AppImage/Flatpak 等打包方式下
+/// 也能被系统正确路由到本程序（Tauri 插件的进程内注册不会持久化）
+#[cfg(target_os = "linux")]
+fn fix_deep_link_desktop_entry() -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::Write;
+
+    let exe_path = if let Ok(appimage) = std::env::var("APPIMAGE") {
+        std::path::PathBuf::from(appimage)
+    } else {
+        std::env::current_exe()?
+    };
+    let exe_str = exe_path.to_string_lossy();
+
+    let apps_dir = dirs::data_dir()
+        .ok_or("无法定位 XDG data 目录")?
+        .join("applications");
+    std::fs::create_dir_all(&apps_dir)?;
+
+    let desktop_file = apps_dir.join("kiro-account-manager.desktop");
+    let contents = format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name=Kiro Account Manager\n\
+         Exec=\"{}\" --open-url -- %u\n\
+         NoDisplay=true\n\
+         MimeType=x-scheme-handler/kiro;\n",
+        exe_str
+    );
+    let mut file = std::fs::File::create(&desktop_file)?;
+    file.write_all(contents.as_bytes())?;
+
+    println!("[DesktopEntry] Wrote {}", desktop_file.display());
+
+    // 这两个工具在某些最小化发行版 / Flatpak 沙箱里可能不存在，失败时直接忽略
+    let _ = std::process::Command::new("update-desktop-database")
+        .arg(&apps_dir)
+        .status();
+    let _ = std::process::Command::new("xdg-mime")
+        .args(["default", "kiro-account-manager.desktop", "x-scheme-handler/kiro"])
+        .status();
+
+    Ok(())
+}
+
 /// Windows: 修复注册表中的 deep link 格式
 /// Tauri 自动注册的格式缺少 --open-url 和 -- 参数
 #[cfg(windows)]
@@ -88,21 +134,10 @@ fn main() {
                 println!("[SingleInstance] args[{}] = {}", i, arg);
             }
             
-            // Windows 点击 kiro:// 链接时启动新实例
-            // 参数格式可能是:
-            // - [exe_path, --open-url, --, url] (4个参数，URL在args[3])
-            // - [exe_path, url] (2个参数，URL在args[1])
-            // - [exe_path, --open-url, url] (3个参数，URL在args[2])
-            let url = if args.len() > 3 {
-                Some(&args[3])
-            } else if args.len() > 1 && args[1].starts_with("kiro://") {
-                Some(&args[1])
-            } else if args.len() > 2 && args[2].starts_with("kiro://") {
-                Some(&args[2])
-            } else {
-                None
-            };
-            
+            // Windows 点击 kiro:// 链接时启动新实例，具体的参数形状交给
+            // deep_link_handler::extract_url 统一识别，这里不再猜下标
+            let url = deep_link_handler::extract_url(args);
+
             if let Some(url) = url {
                 println!("[SingleInstance] ✓ Found URL: {}", url);
                 println!("[SingleInstance] Emitting deep-link://new-url event...");
@@ -124,7 +159,10 @@ fn main() {
         }))
         .setup(|app| {
             // 注册 deep link 协议 (kiro://)
-            #[cfg(any(target_os = "linux", windows))]
+            // macOS 下 bundle 的 CFBundleURLTypes 关联仍然来自 tauri.conf.json；
+            // kiro:// 回调以 RunEvent::Opened 的形式到达，由 main() 末尾 run()
+            // 里的处理转发成 deep-link://new-url，register() 的返回值在这里被忽略
+            #[cfg(any(target_os = "linux", target_os = "macos", windows))]
             {
                 use tauri_plugin_deep_link::DeepLinkExt;
                 let _ = app.deep_link().register("kiro");
@@ -139,6 +177,15 @@ fn main() {
                     Err(e) => println!("[Setup] ✗ Registry update failed: {}", e),
                 }
             }
+
+            // Linux: 写入 .desktop 文件，弥补插件运行时注册不持久化的问题
+            #[cfg(target_os = "linux")]
+            {
+                match fix_deep_link_desktop_entry() {
+                    Ok(_) => println!("[Setup] ✓ Desktop entry updated successfully"),
+                    Err(e) => println!("[Setup] ✗ Desktop entry update failed: {}", e),
+                }
+            }
             
             // 监听 deep link URL 事件
             let app_handle = app.handle().clone();
@@ -148,25 +195,21 @@ fn main() {
                 println!("[DeepLink] Raw payload: {}", payload);
                 println!("[DeepLink] Payload length: {}", payload.len());
                 
-                // Tauri 事件 payload 是 JSON 格式，需要反序列化
-                // payload 格式: "\"kiro://...\"" (包含转义引号)
-                let url: String = match serde_json::from_str(payload) {
-                    Ok(u) => {
-                        println!("[DeepLink] ✓ JSON parsed URL: {}", u);
-                        u
-                    }
-                    Err(e) => {
-                        println!("[DeepLink] ✗ JSON parse failed: {}", e);
-                        println!("[DeepLink] Using raw payload as URL");
-                        payload.to_string()
-                    }
-                };
-                
+                // payload 格式是 JSON 编码的字符串（"\"kiro://...\""），
+                // 统一交给 deep_link_handler::parse_event_payload 去反序列化
+                let url = deep_link_handler::parse_event_payload(payload);
+                println!("[DeepLink] Parsed URL: {}", url);
+
                 // 处理 OAuth 回调
                 println!("[DeepLink] Calling handle_deep_link with URL: {}", url);
                 let handled = deep_link_handler::handle_deep_link(&url);
                 println!("[DeepLink] Handle result: {}", if handled { "✓ SUCCESS" } else { "✗ FAILED" });
-                
+                if handled {
+                    // 识别出具体是哪个 provider 的回调（目前只接了通用 OIDC），
+                    // 并真正把授权码换成 token，而不是只打日志
+                    deep_link_handler::route_deep_link(&app_handle, &url);
+                }
+
                 // 聚焦窗口
                 if let Some(window) = app_handle.get_webview_window("main") {
                     let _ = window.set_focus();
@@ -174,6 +217,12 @@ fn main() {
                 println!("========== END DEEP LINK EVENT ==========\n");
             });
             
+            // 本地 IPC 服务：让 kiro-cli 等外部进程可以在 GUI 不聚焦时切换账号
+            ipc::spawn(app.handle().clone());
+
+            // 本地令牌注入代理：被 set_kiro_proxy 指向后，由它负责账号轮换和 token 续期
+            proxy_server::spawn(app.handle().clone());
+
             Ok(())
         })
         .manage(AppState {
@@ -202,6 +251,12 @@ fn main() {
             get_supported_providers,
             handle_kiro_social_callback,
             add_kiro_account,
+            // 通用 OIDC / IndieAuth provider。get_supported_providers 本应把
+            // list_custom_oidc_providers 的结果合并进它返回的 provider 列表，
+            // 但那个函数所在的文件不在本次改动范围内，先保留一个独立的查询
+            // 命令，确保用户添加过的自定义 provider 至少能被查到
+            oidc_provider::add_account_by_oidc,
+            oidc_provider::list_custom_oidc_providers,
             // Kiro IDE 命令
             get_kiro_local_token,
             switch_kiro_account,
@@ -253,6 +308,7 @@ fn main() {
             uninstall_power,
             // 代理检测命令
             detect_system_proxy,
+            proxy_server::get_local_proxy_url,
             // SSO Token 导入命令
             import_from_sso_token,
             // 更新检查命令
@@ -264,6 +320,18 @@ fn main() {
             delete_steering_file,
             create_steering_file
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            // macOS/iOS 的 kiro:// 回调是通过 apple-event 的 openURL 走到这里的，
+            // 插件本身不会像 Linux/Windows 那样发出 deep-link://new-url，
+            // 所以要自己把 RunEvent::Opened 转发成同一个事件，好让已有的
+            // setup() 监听器和 deep_link_handler 继续处理剩下的流程
+            if let tauri::RunEvent::Opened { urls } = event {
+                for url in urls {
+                    println!("[AppleEvent] kiro:// open-url callback: {}", url);
+                    let _ = app_handle.emit("deep-link://new-url", url.to_string());
+                }
+            }
+        });
 }
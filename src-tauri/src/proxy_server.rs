@@ -0,0 +1,248 @@
+//! 本地令牌注入反向代理：在本机回环端口上起一个 Tokio 异步代理，
+//! 转发 CodeWhisperer / Kiro API 流量的同时，把当前选中账号的 bearer token
+//! 注入到请求头里；遇到 403/429/quota-exhausted 时自动轮换到下一个健康账号，
+//! 并在重试前按账号的 provider 分派刷新逻辑、换一个新 token 再试。
+//!
+//! 通过 `set_kiro_proxy` 把 Kiro IDE 的代理设置指向这里，IDE 侧就不再需要关心
+//! 多账号轮换和 token 续期。
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Client, Request, Response, Server, StatusCode, Uri};
+use hyper_tls::HttpsConnector;
+use tauri::{AppHandle, Manager};
+use tokio::sync::RwLock;
+
+use crate::account::{Account, RefreshedToken};
+use crate::state::AppState;
+
+/// 某个账号触发限流后的冷却时长，在此期间轮换时会跳过它
+const COOLDOWN: Duration = Duration::from_secs(60);
+
+/// 代理监听的本地端口；固定端口方便 `set_kiro_proxy` 直接拼出 URL
+const PROXY_PORT: u16 = 38765;
+
+struct ProxyState {
+    app_handle: AppHandle,
+    /// 当前用于注入 token 的账号在账号列表里的下标
+    active_index: RwLock<usize>,
+    /// account_id -> 冷却截止时间，用于跳过被限流的账号
+    cooldowns: RwLock<HashMap<String, Instant>>,
+    // upstream 是 https://codewhisperer...，裸 HttpConnector 不会说 TLS，必须走 HttpsConnector
+    http_client: Client<HttpsConnector<hyper::client::HttpConnector>>,
+}
+
+/// 在 `setup()` 里调用，把代理放到一个独立的 Tokio 运行时线程里跑
+pub fn spawn(app_handle: AppHandle) {
+    std::thread::spawn(move || {
+        let rt = match tokio::runtime::Runtime::new() {
+            Ok(rt) => rt,
+            Err(e) => {
+                eprintln!("[Proxy] failed to start tokio runtime: {}", e);
+                return;
+            }
+        };
+        rt.block_on(run(app_handle));
+    });
+}
+
+/// 代理监听地址，暴露给 `set_kiro_proxy` 拼 URL 用
+pub fn local_proxy_url() -> String {
+    format!("http://127.0.0.1:{}", PROXY_PORT)
+}
+
+async fn run(app_handle: AppHandle) {
+    let state = Arc::new(ProxyState {
+        app_handle,
+        active_index: RwLock::new(0),
+        cooldowns: RwLock::new(HashMap::new()),
+        http_client: Client::builder().build(HttpsConnector::new()),
+    });
+
+    let make_svc = make_service_fn(move |_conn| {
+        let state = state.clone();
+        async move {
+            Ok::<_, hyper::Error>(service_fn(move |req| {
+                let state = state.clone();
+                async move { Ok::<_, hyper::Error>(handle(state, req).await) }
+            }))
+        }
+    });
+
+    let addr = ([127, 0, 0, 1], PROXY_PORT).into();
+    println!("[Proxy] listening on {}", local_proxy_url());
+    if let Err(e) = Server::bind(&addr).serve(make_svc).await {
+        eprintln!("[Proxy] server error: {}", e);
+    }
+}
+
+async fn handle(state: Arc<ProxyState>, req: Request<Body>) -> Response<Body> {
+    let (parts, body) = req.into_parts();
+    // 请求体要在重试之间复用，先整体读进内存（CodeWhisperer/Kiro API 的请求体很小，
+    // 都是 JSON），不能像第一版那样在每次重试时用 Body::empty() 把它扔掉
+    let body_bytes = match hyper::body::to_bytes(body).await {
+        Ok(b) => b,
+        Err(e) => return error_response(StatusCode::BAD_GATEWAY, &format!("failed to read request body: {}", e)),
+    };
+
+    // connection-per-request：每个请求独立挑一次账号、独立重试，互不影响
+    for attempt in 0..2 {
+        let account_id = match pick_account_id(&state).await {
+            Some(id) => id,
+            None => {
+                return error_response(StatusCode::SERVICE_UNAVAILABLE, "no healthy account available");
+            }
+        };
+
+        let token = match ensure_fresh_token(&state, &account_id).await {
+            Ok(t) => t,
+            // 刷新失败：把这个账号标记为不健康并轮换到下一个再试一次
+            Err(_) if attempt == 0 => {
+                mark_cooldown(&state, &account_id).await;
+                rotate(&state).await;
+                continue;
+            }
+            Err(e) => return error_response(StatusCode::BAD_GATEWAY, &e),
+        };
+
+        let upstream_req = match build_upstream_request(&parts, body_bytes.clone(), &token) {
+            Ok(r) => r,
+            Err(e) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, &e),
+        };
+
+        match state.http_client.request(upstream_req).await {
+            Ok(resp) if is_rate_limited(resp.status()) && attempt == 0 => {
+                mark_cooldown(&state, &account_id).await;
+                rotate(&state).await;
+                continue;
+            }
+            Ok(resp) => return resp,
+            Err(e) => return error_response(StatusCode::BAD_GATEWAY, &e.to_string()),
+        }
+    }
+
+    error_response(StatusCode::SERVICE_UNAVAILABLE, "all accounts are rate limited")
+}
+
+fn is_rate_limited(status: StatusCode) -> bool {
+    status == StatusCode::FORBIDDEN
+        || status == StatusCode::TOO_MANY_REQUESTS
+        || status.as_u16() == 529 // quota-exhausted
+}
+
+async fn pick_account_id(state: &ProxyState) -> Option<String> {
+    let app_state = state.app_handle.state::<AppState>();
+    let store = app_state.store.lock().ok()?;
+    let accounts = store.list();
+    if accounts.is_empty() {
+        return None;
+    }
+
+    let cooldowns = state.cooldowns.read().await;
+    let now = Instant::now();
+    let index = *state.active_index.read().await;
+
+    (0..accounts.len())
+        .map(|offset| (index + offset) % accounts.len())
+        .find(|i| {
+            cooldowns
+                .get(&accounts[*i].id)
+                .map(|until| now >= *until)
+                .unwrap_or(true)
+        })
+        .map(|i| accounts[i].id.clone())
+}
+
+async fn rotate(state: &ProxyState) {
+    let app_state = state.app_handle.state::<AppState>();
+    let len = app_state
+        .store
+        .lock()
+        .map(|s| s.list().len())
+        .unwrap_or(1)
+        .max(1);
+    let mut index = state.active_index.write().await;
+    *index = (*index + 1) % len;
+}
+
+async fn mark_cooldown(state: &ProxyState, account_id: &str) {
+    let mut cooldowns = state.cooldowns.write().await;
+    cooldowns.insert(account_id.to_string(), Instant::now() + COOLDOWN);
+}
+
+/// 过期就先刷新再返回 token。刷新请求要打网络，不能在持有 `std::sync::Mutex`
+/// 的情况下 `.await`（会跨越 await 点持锁），所以这里先拿一份账号快照、释放锁、
+/// 刷新完再重新拿锁写回去
+async fn ensure_fresh_token(state: &ProxyState, account_id: &str) -> Result<String, String> {
+    let app_state = state.app_handle.state::<AppState>();
+
+    let needs_refresh = {
+        let store = app_state.store.lock().map_err(|e| e.to_string())?;
+        store.token_expired(account_id)
+    };
+
+    if needs_refresh {
+        let account = {
+            let store = app_state.store.lock().map_err(|e| e.to_string())?;
+            store.account(account_id).ok_or_else(|| format!("unknown account `{}`", account_id))?
+        };
+        let refreshed = refresh_provider_token(&account).await?;
+        let mut store = app_state.store.lock().map_err(|e| e.to_string())?;
+        store.apply_refreshed_token(account_id, refreshed)?;
+    }
+
+    let store = app_state.store.lock().map_err(|e| e.to_string())?;
+    store.access_token(account_id).ok_or_else(|| "account has no token".to_string())
+}
+
+/// 按账号的 provider 把刷新请求分派到对应的刷新客户端。目前只有本次改动里加的
+/// 通用 OIDC provider 是我们能看到完整刷新流程的；内置的社交登录 / IdC provider
+/// 的刷新客户端不在这次改动涉及的文件里，这里诚实地报错让调用方把这个账号标记
+/// 为不健康并轮换到下一个，而不是假装刷新成功、拿着过期 token 再打一次上游
+async fn refresh_provider_token(account: &Account) -> Result<RefreshedToken, String> {
+    if crate::oidc_provider::is_custom_oidc_provider(&account.provider) {
+        crate::oidc_provider::refresh_oidc_account(account).await
+    } else {
+        Err(format!(
+            "refreshing `{}` accounts isn't implemented here yet; re-authenticate the account instead",
+            account.provider
+        ))
+    }
+}
+
+fn build_upstream_request(
+    parts: &hyper::http::request::Parts,
+    body: hyper::body::Bytes,
+    token: &str,
+) -> Result<Request<Body>, String> {
+    // CodeWhisperer / Kiro API 的上游地址固定，本地代理只负责换 Host、注入 Authorization
+    // 并原样转发请求体
+    let upstream_uri: Uri = format!("https://codewhisperer.us-east-1.amazonaws.com{}", parts.uri)
+        .parse()
+        .map_err(|e| format!("bad upstream uri: {}", e))?;
+
+    let mut builder = Request::builder().method(parts.method.clone()).uri(upstream_uri);
+    for (name, value) in parts.headers.iter() {
+        if name != hyper::header::HOST && name != hyper::header::AUTHORIZATION {
+            builder = builder.header(name, value);
+        }
+    }
+    builder = builder.header(hyper::header::AUTHORIZATION, format!("Bearer {}", token));
+    builder.body(Body::from(body)).map_err(|e| e.to_string())
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .body(Body::from(message.to_string()))
+        .unwrap_or_else(|_| Response::new(Body::from(message.to_string())))
+}
+
+/// 供 `commands::proxy_cmd` 调用，把 Kiro IDE 的代理指向本地注入代理
+#[tauri::command]
+pub fn get_local_proxy_url() -> String {
+    local_proxy_url()
+}